@@ -0,0 +1,517 @@
+//! Correlation-and-diff engine for pairing primary/shadow observations that
+//! arrive independently (over Kafka or the HTTP log endpoints) and producing
+//! a [`ComparisonResult`] once both sides of a `request_id` are known, or
+//! once the pairing times out and is flushed as an orphan.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tokio::time::Duration;
+use tracing::{error, warn};
+
+use crate::{MonitorEvent, RelayLog, RelayOutcome, RequestLog};
+
+/// How long a pairing waits for its missing side before being flushed as an
+/// orphan result.
+pub const PAIR_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default ignore-list of JSON-pointer path globs for volatile fields that
+/// shouldn't produce false diffs between primary and shadow bodies.
+pub fn default_ignored_paths() -> Vec<String> {
+    vec![
+        "/timestamp".to_string(),
+        "/request_id".to_string(),
+        "/*/id".to_string(),
+    ]
+}
+
+#[derive(Debug, Clone)]
+struct Observation {
+    status: u16,
+    body: String,
+    body_encoding: String,
+    response_time_ms: u64,
+    outcome: RelayOutcome,
+    /// Only meaningful on the primary side: whether this request's route
+    /// has a shadow upstream configured at all, i.e. whether a shadow
+    /// observation is actually coming.
+    shadow_expected: bool,
+}
+
+impl Observation {
+    fn from_relay_log(log: &RelayLog) -> Self {
+        Observation {
+            status: log.response_status,
+            body: log.response_body.clone(),
+            body_encoding: log.response_body_encoding.clone(),
+            response_time_ms: log.response_time_ms,
+            outcome: log.outcome.clone(),
+            shadow_expected: log.shadow_expected,
+        }
+    }
+
+    fn from_request_log(log: &RequestLog) -> Self {
+        Observation {
+            status: log.response_status,
+            body: log.response_body.clone(),
+            body_encoding: "utf8".to_string(),
+            response_time_ms: log.response_time_ms,
+            outcome: log.outcome.clone(),
+            shadow_expected: log.shadow_expected,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PendingPair {
+    primary: Option<Observation>,
+    shadow: Option<Observation>,
+    first_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    ValueMismatch,
+    TypeMismatch,
+    MissingOnPrimary,
+    MissingOnShadow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diff {
+    pub path: String,
+    pub kind: DiffKind,
+    pub primary_value: Option<Value>,
+    pub shadow_value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonResult {
+    pub request_id: String,
+    pub status_match: bool,
+    pub latency_delta_ms: i64,
+    pub body_match: bool,
+    pub diffs: Vec<Diff>,
+    pub orphan: bool,
+}
+
+/// Pairs primary/shadow observations by `request_id` and emits
+/// [`ComparisonResult`]s (and Kafka alerts) once a pair is complete or its
+/// timeout has elapsed.
+pub struct ComparisonEngine {
+    pending: DashMap<String, PendingPair>,
+    ignored_paths: Vec<String>,
+    kafka_producer: Arc<FutureProducer>,
+    event_tx: broadcast::Sender<MonitorEvent>,
+}
+
+impl ComparisonEngine {
+    pub fn new(
+        kafka_producer: Arc<FutureProducer>,
+        ignored_paths: Vec<String>,
+        event_tx: broadcast::Sender<MonitorEvent>,
+    ) -> Self {
+        ComparisonEngine {
+            pending: DashMap::new(),
+            ignored_paths,
+            kafka_producer,
+            event_tx,
+        }
+    }
+
+    pub async fn record_relay_log(&self, log: &RelayLog) {
+        self.record(&log.request_id, &log.role, Observation::from_relay_log(log))
+            .await;
+    }
+
+    /// `RequestLog`s arrive from the primary service's own instrumentation,
+    /// so they always fill the primary slot of a pairing.
+    pub async fn record_request_log(&self, log: &RequestLog) {
+        self.record(&log.request_id, "primary", Observation::from_request_log(log))
+            .await;
+    }
+
+    async fn record(&self, request_id: &str, role: &str, observation: Observation) {
+        let ready = {
+            let mut pair = self.pending.entry(request_id.to_string()).or_insert_with(|| PendingPair {
+                first_seen: Some(Utc::now()),
+                ..Default::default()
+            });
+            if role == "shadow" {
+                pair.shadow = Some(observation);
+            } else {
+                pair.primary = Some(observation);
+            }
+            pair.primary.is_some() && pair.shadow.is_some()
+        };
+
+        if ready {
+            if let Some((_, pair)) = self.pending.remove(request_id) {
+                self.finish(request_id, pair, false).await;
+            }
+        }
+    }
+
+    /// Flushes any pairing that has been waiting longer than
+    /// [`PAIR_TIMEOUT`] as an orphan result. Intended to be called
+    /// periodically from a background task.
+    pub async fn sweep_expired(&self) {
+        let now = Utc::now();
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|entry| match entry.value().first_seen {
+                Some(first_seen) => (now - first_seen).to_std().unwrap_or_default() >= PAIR_TIMEOUT,
+                None => false,
+            })
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        for request_id in expired {
+            if let Some((_, pair)) = self.pending.remove(&request_id) {
+                self.finish(&request_id, pair, true).await;
+            }
+        }
+    }
+
+    async fn finish(&self, request_id: &str, pair: PendingPair, timed_out: bool) {
+        // A request whose route has no shadow upstream configured will
+        // never get a shadow observation - that's not a missing pairing,
+        // it's the expected shape of single-upstream traffic. Drop it
+        // silently instead of flushing it as an "orphan" and firing a
+        // comparison-alerts alert for every normal request.
+        if let (Some(primary), None) = (&pair.primary, &pair.shadow) {
+            if !primary.shadow_expected {
+                return;
+            }
+        }
+
+        let result = match (pair.primary, pair.shadow) {
+            (Some(primary), Some(shadow))
+                if matches!(primary.outcome, RelayOutcome::Error { .. })
+                    || matches!(shadow.outcome, RelayOutcome::Error { .. }) =>
+            {
+                // One side never got a real response to compare - don't run
+                // the JSON diff, just surface which side crashed.
+                ComparisonResult {
+                    request_id: request_id.to_string(),
+                    status_match: false,
+                    latency_delta_ms: shadow.response_time_ms as i64 - primary.response_time_ms as i64,
+                    body_match: false,
+                    diffs: vec![Diff {
+                        path: String::new(),
+                        kind: DiffKind::ValueMismatch,
+                        primary_value: Some(outcome_summary(&primary.outcome)),
+                        shadow_value: Some(outcome_summary(&shadow.outcome)),
+                    }],
+                    orphan: false,
+                }
+            }
+            (Some(primary), Some(shadow)) => {
+                let status_match = primary.status == shadow.status;
+                // Binary (base64-encoded) bodies can't be meaningfully walked
+                // as JSON - fall back to comparing them as opaque blobs.
+                let diffs = if primary.body_encoding == "utf8" && shadow.body_encoding == "utf8" {
+                    diff_bodies(&primary.body, &shadow.body, &self.ignored_paths)
+                } else if primary.body == shadow.body {
+                    Vec::new()
+                } else {
+                    vec![Diff {
+                        path: String::new(),
+                        kind: DiffKind::ValueMismatch,
+                        primary_value: Some(Value::String(format!("<{} body>", primary.body_encoding))),
+                        shadow_value: Some(Value::String(format!("<{} body>", shadow.body_encoding))),
+                    }]
+                };
+                ComparisonResult {
+                    request_id: request_id.to_string(),
+                    status_match,
+                    latency_delta_ms: shadow.response_time_ms as i64 - primary.response_time_ms as i64,
+                    body_match: diffs.is_empty(),
+                    diffs,
+                    orphan: false,
+                }
+            }
+            _ => ComparisonResult {
+                request_id: request_id.to_string(),
+                status_match: false,
+                latency_delta_ms: 0,
+                body_match: false,
+                diffs: Vec::new(),
+                orphan: true,
+            },
+        };
+
+        tracing::info!(
+            "Comparison result for {}: status_match={}, body_match={}, orphan={}, timed_out={}",
+            request_id,
+            result.status_match,
+            result.body_match,
+            result.orphan,
+            timed_out
+        );
+
+        // Best-effort: no one may be subscribed to the SSE stream right now.
+        let _ = self.event_tx.send(MonitorEvent::Comparison(result.clone()));
+
+        if result.orphan || !result.status_match || !result.diffs.is_empty() {
+            self.emit_alert(&result).await;
+        }
+    }
+
+    async fn emit_alert(&self, result: &ComparisonResult) {
+        warn!(
+            "Comparison alert for request_id={}: status_match={}, body_match={}, orphan={}, diffs={}",
+            result.request_id,
+            result.status_match,
+            result.body_match,
+            result.orphan,
+            result.diffs.len()
+        );
+
+        let payload = match serde_json::to_string(result) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize comparison alert: {}", e);
+                return;
+            }
+        };
+
+        let record = FutureRecord::to("comparison-alerts")
+            .key(&result.request_id)
+            .payload(&payload);
+
+        if let Err((kafka_error, _)) = self.kafka_producer.send(record, Duration::from_secs(0)).await {
+            error!("Failed to send comparison alert to Kafka: {:?}", kafka_error);
+        }
+    }
+}
+
+/// A short JSON summary of an outcome, used as the diff value when a pairing
+/// can't be body-compared because one side errored out.
+fn outcome_summary(outcome: &RelayOutcome) -> Value {
+    match outcome {
+        RelayOutcome::Success => Value::String("success".to_string()),
+        RelayOutcome::BadResponse { status, .. } => Value::String(format!("bad_response({})", status)),
+        RelayOutcome::Error { kind, message } => Value::String(format!("error({:?}): {}", kind, message)),
+    }
+}
+
+/// Compares two response bodies. If both parse as JSON, walks them
+/// recursively collecting the JSON-pointer path of every mismatch; otherwise
+/// falls back to byte/string equality.
+pub fn diff_bodies(primary_body: &str, shadow_body: &str, ignored_paths: &[String]) -> Vec<Diff> {
+    match (
+        serde_json::from_str::<Value>(primary_body),
+        serde_json::from_str::<Value>(shadow_body),
+    ) {
+        (Ok(primary), Ok(shadow)) => {
+            let mut diffs = Vec::new();
+            walk_json(&primary, &shadow, String::new(), ignored_paths, &mut diffs);
+            diffs
+        }
+        _ if primary_body == shadow_body => Vec::new(),
+        _ => vec![Diff {
+            path: String::new(),
+            kind: DiffKind::ValueMismatch,
+            primary_value: Some(Value::String(primary_body.to_string())),
+            shadow_value: Some(Value::String(shadow_body.to_string())),
+        }],
+    }
+}
+
+fn walk_json(primary: &Value, shadow: &Value, path: String, ignored: &[String], diffs: &mut Vec<Diff>) {
+    if is_ignored(&path, ignored) {
+        return;
+    }
+
+    match (primary, shadow) {
+        (Value::Object(p_map), Value::Object(s_map)) => {
+            for (key, p_value) in p_map {
+                let child_path = format!("{}/{}", path, key);
+                match s_map.get(key) {
+                    Some(s_value) => walk_json(p_value, s_value, child_path, ignored, diffs),
+                    None => {
+                        if !is_ignored(&child_path, ignored) {
+                            diffs.push(Diff {
+                                path: child_path,
+                                kind: DiffKind::MissingOnShadow,
+                                primary_value: Some(p_value.clone()),
+                                shadow_value: None,
+                            });
+                        }
+                    }
+                }
+            }
+            for (key, s_value) in s_map {
+                if p_map.contains_key(key) {
+                    continue;
+                }
+                let child_path = format!("{}/{}", path, key);
+                if is_ignored(&child_path, ignored) {
+                    continue;
+                }
+                diffs.push(Diff {
+                    path: child_path,
+                    kind: DiffKind::MissingOnPrimary,
+                    primary_value: None,
+                    shadow_value: Some(s_value.clone()),
+                });
+            }
+        }
+        (Value::Array(p_arr), Value::Array(s_arr)) => {
+            for (i, (p_value, s_value)) in p_arr.iter().zip(s_arr.iter()).enumerate() {
+                walk_json(p_value, s_value, format!("{}/{}", path, i), ignored, diffs);
+            }
+            if p_arr.len() != s_arr.len() {
+                diffs.push(Diff {
+                    path,
+                    kind: DiffKind::ValueMismatch,
+                    primary_value: Some(Value::from(p_arr.len())),
+                    shadow_value: Some(Value::from(s_arr.len())),
+                });
+            }
+        }
+        _ => {
+            if std::mem::discriminant(primary) != std::mem::discriminant(shadow) {
+                diffs.push(Diff {
+                    path,
+                    kind: DiffKind::TypeMismatch,
+                    primary_value: Some(primary.clone()),
+                    shadow_value: Some(shadow.clone()),
+                });
+            } else if primary != shadow {
+                diffs.push(Diff {
+                    path,
+                    kind: DiffKind::ValueMismatch,
+                    primary_value: Some(primary.clone()),
+                    shadow_value: Some(shadow.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Matches a JSON-pointer path against an ignore-list glob where `*`
+/// segments match exactly one path component (e.g. `/*/id` matches
+/// `/users/id` but not `/id` or `/a/b/id`).
+fn is_ignored(path: &str, ignored: &[String]) -> bool {
+    ignored.iter().any(|pattern| glob_match_pointer(pattern, path))
+}
+
+fn glob_match_pointer(pattern: &str, path: &str) -> bool {
+    let pattern_parts: Vec<&str> = pattern.split('/').collect();
+    let path_parts: Vec<&str> = path.split('/').collect();
+    pattern_parts.len() == path_parts.len()
+        && pattern_parts
+            .iter()
+            .zip(path_parts.iter())
+            .all(|(p, s)| *p == "*" || p == s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_pointer_matches_single_wildcard_segment() {
+        assert!(glob_match_pointer("/*/id", "/users/id"));
+        assert!(!glob_match_pointer("/*/id", "/id"));
+        assert!(!glob_match_pointer("/*/id", "/a/b/id"));
+    }
+
+    #[test]
+    fn glob_match_pointer_requires_exact_literal_segments() {
+        assert!(glob_match_pointer("/timestamp", "/timestamp"));
+        assert!(!glob_match_pointer("/timestamp", "/timestamps"));
+    }
+
+    #[test]
+    fn is_ignored_checks_every_pattern_in_the_list() {
+        let ignored = default_ignored_paths();
+        assert!(is_ignored("/timestamp", &ignored));
+        assert!(is_ignored("/request_id", &ignored));
+        assert!(is_ignored("/users/id", &ignored));
+        assert!(!is_ignored("/users/name", &ignored));
+    }
+
+    #[test]
+    fn walk_json_flags_value_mismatch() {
+        let primary = serde_json::json!({"a": 1});
+        let shadow = serde_json::json!({"a": 2});
+        let mut diffs = Vec::new();
+        walk_json(&primary, &shadow, String::new(), &[], &mut diffs);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "/a");
+        assert_eq!(diffs[0].kind, DiffKind::ValueMismatch);
+    }
+
+    #[test]
+    fn walk_json_flags_type_mismatch() {
+        let primary = serde_json::json!({"a": 1});
+        let shadow = serde_json::json!({"a": "1"});
+        let mut diffs = Vec::new();
+        walk_json(&primary, &shadow, String::new(), &[], &mut diffs);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].kind, DiffKind::TypeMismatch);
+    }
+
+    #[test]
+    fn walk_json_flags_keys_missing_on_either_side() {
+        let primary = serde_json::json!({"a": 1, "b": 2});
+        let shadow = serde_json::json!({"a": 1, "c": 3});
+        let mut diffs = Vec::new();
+        walk_json(&primary, &shadow, String::new(), &[], &mut diffs);
+        diffs.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].path, "/b");
+        assert_eq!(diffs[0].kind, DiffKind::MissingOnShadow);
+        assert_eq!(diffs[1].path, "/c");
+        assert_eq!(diffs[1].kind, DiffKind::MissingOnPrimary);
+    }
+
+    #[test]
+    fn walk_json_flags_array_length_mismatch() {
+        let primary = serde_json::json!({"a": [1, 2, 3]});
+        let shadow = serde_json::json!({"a": [1, 2]});
+        let mut diffs = Vec::new();
+        walk_json(&primary, &shadow, String::new(), &[], &mut diffs);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "/a");
+        assert_eq!(diffs[0].kind, DiffKind::ValueMismatch);
+    }
+
+    #[test]
+    fn walk_json_respects_ignore_list() {
+        let primary = serde_json::json!({"id": 1, "timestamp": "t0"});
+        let shadow = serde_json::json!({"id": 2, "timestamp": "t1"});
+        let ignored = vec!["/id".to_string(), "/timestamp".to_string()];
+        let mut diffs = Vec::new();
+        walk_json(&primary, &shadow, String::new(), &ignored, &mut diffs);
+        assert!(diffs.is_empty());
+    }
+
+    #[test]
+    fn diff_bodies_falls_back_to_string_equality_for_non_json() {
+        assert!(diff_bodies("not json", "not json", &[]).is_empty());
+        let diffs = diff_bodies("not json", "also not json", &[]);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].kind, DiffKind::ValueMismatch);
+    }
+
+    #[test]
+    fn diff_bodies_finds_nested_mismatches_with_json_pointer_paths() {
+        let primary = r#"{"user": {"id": 1, "name": "alice"}}"#;
+        let shadow = r#"{"user": {"id": 2, "name": "bob"}}"#;
+        let ignored = vec!["/*/id".to_string()];
+        let diffs = diff_bodies(primary, shadow, &ignored);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "/user/name");
+    }
+}
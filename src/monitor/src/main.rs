@@ -1,27 +1,41 @@
+mod comparison;
+
 use axum::{
     routing::{get, post},
     Router,
     Json,
-    extract::State,
+    extract::{Query, State},
+    response::sse::{Event, Sse},
 };
+use futures::Stream;
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use rdkafka::config::ClientConfig;
 use rdkafka::consumer::{StreamConsumer, Consumer};
+use rdkafka::producer::FutureProducer;
 use rdkafka::Message;
+use tokio::sync::broadcast;
 use tokio::time::Duration;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use comparison::{ComparisonEngine, ComparisonResult};
+
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+const SSE_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
 #[derive(Clone)]
 pub struct AppState {
     pub kafka_consumer: Arc<StreamConsumer>,
+    pub comparison_engine: Arc<ComparisonEngine>,
+    pub event_tx: broadcast::Sender<MonitorEvent>,
     pub shutdown: Arc<AtomicBool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestLog {
     pub request_id: String,
     pub service_name: String,
@@ -30,13 +44,52 @@ pub struct RequestLog {
     pub path: String,
     pub request_headers: std::collections::HashMap<String, String>,
     pub request_body: Option<String>,
+    /// Defaults to `Success` when absent so existing primary-service
+    /// producers that POST to `/log/request` without an `outcome` field
+    /// (predating chunk0-5) keep logging instead of failing deserialization.
+    #[serde(default)]
+    pub outcome: RelayOutcome,
     pub response_status: u16,
     pub response_headers: std::collections::HashMap<String, String>,
     pub response_body: String,
     pub response_time_ms: u64,
+    /// Whether the route this request took has a shadow upstream configured
+    /// at all; see [`RelayLog::shadow_expected`]. `RequestLog`s come from
+    /// the primary service's own instrumentation rather than the Relay, so
+    /// this is rarely known - default to `true` so an orphaned primary
+    /// singleton from such a producer still surfaces as a missing-shadow
+    /// alert rather than being silently dropped.
+    #[serde(default = "default_shadow_expected")]
+    pub shadow_expected: bool,
+}
+
+/// What happened when a relay attempt reached its upstream. Mirrors the
+/// Relay's own `RelayOutcome` so the comparison engine can tell "responses
+/// differ" apart from "shadow crashed".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayOutcome {
+    Success,
+    BadResponse { status: u16, body: String },
+    Error { kind: RelayErrorKind, message: String },
+}
+
+impl Default for RelayOutcome {
+    fn default() -> Self {
+        RelayOutcome::Success
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayErrorKind {
+    Connect,
+    Timeout,
+    BodyRead,
+    Other,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnvoyAccessLog {
     // Envoy access log format
     pub start_time: String,
@@ -55,6 +108,65 @@ pub struct EnvoyAccessLog {
     pub authority: Option<String>,
 }
 
+/// Events published onto [`AppState::event_tx`] so dashboards can watch
+/// traffic in real time over the `/events` SSE stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum MonitorEvent {
+    RelayLog(RelayLog),
+    AccessLog(EnvoyAccessLog),
+    Comparison(ComparisonResult),
+}
+
+impl MonitorEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            MonitorEvent::RelayLog(_) => "relay_log",
+            MonitorEvent::AccessLog(_) => "access_log",
+            MonitorEvent::Comparison(_) => "comparison",
+        }
+    }
+
+    fn service_name(&self) -> Option<&str> {
+        match self {
+            MonitorEvent::RelayLog(log) => Some(&log.service_name),
+            MonitorEvent::AccessLog(_) => None,
+            MonitorEvent::Comparison(_) => None,
+        }
+    }
+
+    fn path(&self) -> Option<&str> {
+        match self {
+            MonitorEvent::RelayLog(log) => Some(&log.path),
+            MonitorEvent::AccessLog(log) => Some(&log.path),
+            MonitorEvent::Comparison(_) => None,
+        }
+    }
+
+    fn matches(&self, filter: &EventsQuery) -> bool {
+        // `Comparison` results carry no `service_name`/`path` of their own
+        // (they're keyed by `request_id`, paired from both sides after the
+        // fact), so a `service_name`/`path_prefix` filter can never match
+        // one. Exempt them rather than silently dropping the very alerts a
+        // dashboard subscribes to watch for.
+        if matches!(self, MonitorEvent::Comparison(_)) {
+            return true;
+        }
+        if let Some(service_name) = &filter.service_name {
+            if self.service_name() != Some(service_name.as_str()) {
+                return false;
+            }
+        }
+        if let Some(path_prefix) = &filter.path_prefix {
+            match self.path() {
+                Some(path) if path.starts_with(path_prefix.as_str()) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -81,10 +193,25 @@ async fn main() -> anyhow::Result<()> {
         .subscribe(&["relay-logs"])
         .expect("Can't subscribe to specified topics");
 
+    // Producer used to publish comparison-alerts for the comparison engine
+    let alert_producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &kafka_brokers)
+        .set("message.timeout.ms", "5000")
+        .create()
+        .expect("Producer creation error");
+
     let shutdown = Arc::new(AtomicBool::new(false));
+    let (event_tx, _) = broadcast::channel::<MonitorEvent>(EVENT_CHANNEL_CAPACITY);
+    let comparison_engine = Arc::new(ComparisonEngine::new(
+        Arc::new(alert_producer),
+        comparison::default_ignored_paths(),
+        event_tx.clone(),
+    ));
 
     let state = AppState {
         kafka_consumer: Arc::new(consumer),
+        comparison_engine: comparison_engine.clone(),
+        event_tx,
         shutdown: shutdown.clone(),
     };
 
@@ -94,11 +221,22 @@ async fn main() -> anyhow::Result<()> {
         kafka_consumer_task(consumer_state).await;
     });
 
+    // Periodically flush pairings that never received both sides
+    let sweep_engine = comparison_engine.clone();
+    let sweep_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        while !sweep_shutdown.load(Ordering::Relaxed) {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            sweep_engine.sweep_expired().await;
+        }
+    });
+
     // Build application
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/log/access", post(receive_access_log))
         .route("/log/request", post(receive_request_log))
+        .route("/events", get(sse_events))
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
         .with_state(Arc::new(state));
@@ -117,7 +255,7 @@ async fn health_check() -> &'static str {
 }
 
 async fn receive_access_log(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(log): Json<EnvoyAccessLog>,
 ) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
     info!("Received access log: {} {} -> {}", log.method, log.path, log.response_code);
@@ -125,22 +263,68 @@ async fn receive_access_log(
     // TODO: Store access log to database for primary service monitoring
     // This provides zero-latency monitoring of primary service traffic
 
+    let _ = state.event_tx.send(MonitorEvent::AccessLog(log));
+
     Ok(Json(serde_json::json!({"status": "logged"})))
 }
 
 async fn receive_request_log(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(log): Json<RequestLog>,
 ) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
     info!("Received request log: {} {} -> {} ({}ms)",
           log.method, log.path, log.response_status, log.response_time_ms);
 
-    // TODO: Store detailed request log to database
-    // This can be used for comparison with shadow service logs
+    state.comparison_engine.record_request_log(&log).await;
 
     Ok(Json(serde_json::json!({"status": "logged"})))
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventsQuery {
+    service_name: Option<String>,
+    path_prefix: Option<String>,
+}
+
+async fn sse_events(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<EventsQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let rx = state.event_tx.subscribe();
+
+    let stream = async_stream::stream! {
+        let mut rx = rx;
+        let mut keep_alive = tokio::time::interval(SSE_KEEP_ALIVE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    match msg {
+                        Ok(event) => {
+                            if !event.matches(&filter) {
+                                continue;
+                            }
+                            match serde_json::to_string(&event) {
+                                Ok(data) => yield Ok(Event::default().event(event.name()).data(data)),
+                                Err(e) => error!("Failed to serialize monitor event: {}", e),
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("SSE client lagged, skipped {} events", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+                _ = keep_alive.tick() => {
+                    yield Ok(Event::default().comment("keep-alive"));
+                }
+            }
+        }
+    };
+
+    Sse::new(stream)
+}
+
 async fn kafka_consumer_task(state: AppState) {
     info!("Starting Kafka consumer task for relay logs");
 
@@ -157,12 +341,11 @@ async fn kafka_consumer_task(state: AppState) {
                         Ok(text) => {
                             match serde_json::from_str::<RelayLog>(text) {
                                 Ok(relay_log) => {
-                                    info!("Received relay log from Kafka: request_id={}, method={}, path={}, status={}, response_time={}ms",
-                                          relay_log.request_id, relay_log.method, relay_log.path,
+                                    info!("Received relay log from Kafka: request_id={}, role={}, method={}, path={}, status={}, response_time={}ms",
+                                          relay_log.request_id, relay_log.role, relay_log.method, relay_log.path,
                                           relay_log.response_status, relay_log.response_time_ms);
 
-                                    // TODO: Process and store relay log for comparison
-                                    if let Err(e) = process_relay_log(&relay_log).await {
+                                    if let Err(e) = process_relay_log(&state, &relay_log).await {
                                         error!("Failed to process relay log: {}", e);
                                     }
                                 }
@@ -183,31 +366,45 @@ async fn kafka_consumer_task(state: AppState) {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelayLog {
     pub request_id: String,
     pub service_name: String,
+    pub role: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub method: String,
     pub path: String,
     pub request_headers: std::collections::HashMap<String, String>,
     pub request_body: Option<String>,
+    pub outcome: RelayOutcome,
     pub response_status: u16,
     pub response_headers: std::collections::HashMap<String, String>,
     pub response_body: String,
+    pub response_body_encoding: String,
+    pub response_body_truncated: bool,
     pub response_time_ms: u64,
+    /// Whether the route this request took has a shadow upstream configured
+    /// at all. Lets the comparison engine tell a single-upstream request
+    /// (no shadow ever coming) apart from one whose shadow observation is
+    /// merely late or lost. Defaults to `true` for logs from producers that
+    /// predate this field, preserving today's orphan-alerting behavior for
+    /// them rather than silently going quiet.
+    #[serde(default = "default_shadow_expected")]
+    pub shadow_expected: bool,
 }
 
-async fn process_relay_log(relay_log: &RelayLog) -> anyhow::Result<()> {
-    // TODO: Compare with primary service responses
-    // TODO: Store comparison results
-    // TODO: Generate alerts for significant differences
+fn default_shadow_expected() -> bool {
+    true
+}
 
+async fn process_relay_log(state: &AppState, relay_log: &RelayLog) -> anyhow::Result<()> {
     info!("Processing relay log for comparison: {}", relay_log.request_id);
+    let _ = state.event_tx.send(MonitorEvent::RelayLog(relay_log.clone()));
+    state.comparison_engine.record_relay_log(relay_log).await;
     Ok(())
 }
 
-// TODO: Implement storage and comparison functions:
+// TODO: Implement storage functions:
 
 // async fn store_access_log(log: &EnvoyAccessLog) -> anyhow::Result<()> {
 //     // Store lightweight access logs from Envoy for primary service
@@ -217,10 +414,6 @@ async fn process_relay_log(relay_log: &RelayLog) -> anyhow::Result<()> {
 //     // Store detailed request logs for comparison
 // }
 
-// async fn compare_responses(primary: &RequestLog, shadow: &RelayLog) -> anyhow::Result<()> {
-//     // Compare primary and shadow service responses
-// }
-
 // async fn analyze_primary_performance() -> anyhow::Result<()> {
 //     // Analyze primary service performance without affecting latency
 // }
\ No newline at end of file
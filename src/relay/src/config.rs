@@ -0,0 +1,68 @@
+//! TOML-file-driven configuration for multi-upstream routing. Replaces the
+//! old `TARGET_SERVICE_URL` env var with a `[upstreams]` table of named
+//! (primary, shadow) pairs and a `[[routes]]` table mapping path prefixes to
+//! an upstream name, so one Relay deployment can shadow several services at
+//! once.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Upstream {
+    pub primary: String,
+    pub shadow: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    pub prefix: String,
+    pub upstream: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub default_upstream: String,
+    pub upstreams: HashMap<String, Upstream>,
+    #[serde(default)]
+    pub routes: Vec<Route>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let config: Config = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.upstreams.contains_key(&self.default_upstream) {
+            anyhow::bail!(
+                "default_upstream '{}' is not defined in [upstreams]",
+                self.default_upstream
+            );
+        }
+        for route in &self.routes {
+            if !self.upstreams.contains_key(&route.upstream) {
+                anyhow::bail!(
+                    "route '{}' references unknown upstream '{}'",
+                    route.prefix,
+                    route.upstream
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves the config file path from `RELAY_CONFIG_PATH`, defaulting to
+/// `relay.toml` in the working directory.
+pub fn config_path() -> PathBuf {
+    std::env::var("RELAY_CONFIG_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("relay.toml"))
+}
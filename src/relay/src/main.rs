@@ -1,10 +1,16 @@
+mod config;
+mod routing;
+
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::Json,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
+use base64::Engine as _;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -13,28 +19,142 @@ use tower_http::trace::TraceLayer;
 use tracing::{info, warn, error};
 use rdkafka::config::ClientConfig;
 use rdkafka::producer::{FutureProducer, FutureRecord};
+use tokio::sync::RwLock;
 use tokio::time::Duration;
 
-#[derive(Clone)]
+use config::Config;
+
 pub struct AppState {
-    pub target_service_url: String,
+    pub config: RwLock<Config>,
     pub client: reqwest::Client,
     pub kafka_producer: Arc<FutureProducer>,
 }
 
+/// Response bodies are captured for Kafka logging up to this many bytes;
+/// anything beyond is dropped from the log (but never from what the client
+/// actually receives) and `response_body_truncated` is set.
+const BODY_LOG_CAP_BYTES: usize = 64 * 1024;
+
+/// How many upstream body chunks `dispatch_primary` is allowed to read ahead
+/// of a slow client before it blocks: bounds the extra memory a stalled
+/// client can force us to hold onto, at the cost of `response_time_ms`
+/// absorbing client backpressure once the buffer fills.
+const RELAY_STREAM_CHANNEL_CAPACITY: usize = 32;
+
+/// Headers that are specific to one hop and must not be copied onto the
+/// response we send back to our own caller.
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailers",
+    "transfer-encoding",
+    "upgrade",
+];
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RelayLog {
     pub request_id: String,
     pub service_name: String,
+    pub role: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub method: String,
     pub path: String,
     pub request_headers: HashMap<String, String>,
     pub request_body: Option<String>,
+    pub outcome: RelayOutcome,
     pub response_status: u16,
     pub response_headers: HashMap<String, String>,
     pub response_body: String,
+    pub response_body_encoding: String,
+    pub response_body_truncated: bool,
     pub response_time_ms: u64,
+    /// Whether this route has a shadow upstream configured at all, so the
+    /// Monitor's comparison engine can tell a single-upstream request (no
+    /// shadow ever coming) apart from one whose shadow observation is
+    /// merely late or lost.
+    pub shadow_expected: bool,
+}
+
+/// What happened when the Relay tried to reach an upstream. Carried on every
+/// `RelayLog`, even when the call never reached Kafka before (errors used to
+/// be silently dropped) so the Monitor can tell "responses differ" apart
+/// from "shadow crashed".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayOutcome {
+    Success,
+    BadResponse { status: u16, body: String },
+    Error { kind: RelayErrorKind, message: String },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayErrorKind {
+    Connect,
+    Timeout,
+    BodyRead,
+    Other,
+}
+
+/// Accumulates up to [`BODY_LOG_CAP_BYTES`] of a response body while it's
+/// being streamed through to the caller, so Kafka logging never has to
+/// buffer (or wait on) the full body.
+#[derive(Default)]
+struct CapturedBody {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+impl CapturedBody {
+    fn push(&mut self, chunk: &[u8]) {
+        if self.truncated {
+            return;
+        }
+        let remaining = BODY_LOG_CAP_BYTES.saturating_sub(self.bytes.len());
+        if chunk.len() > remaining {
+            self.bytes.extend_from_slice(&chunk[..remaining]);
+            self.truncated = true;
+        } else {
+            self.bytes.extend_from_slice(chunk);
+        }
+    }
+
+    /// Renders the captured bytes as `(body, encoding, truncated)`, falling
+    /// back to base64 when the bytes aren't valid UTF-8.
+    fn into_log_fields(self) -> (String, String, bool) {
+        if self.truncated {
+            // The cut point is an arbitrary byte offset that may land in the
+            // middle of a multi-byte UTF-8 sequence; back off to the longest
+            // valid UTF-8 prefix before deciding whether this is text or
+            // binary, so a merely-truncated text body isn't mis-tagged
+            // `base64`.
+            let boundary = match std::str::from_utf8(&self.bytes) {
+                Ok(_) => Some(self.bytes.len()),
+                // `error_len() == None`: the only fault is an incomplete
+                // sequence at the very end, i.e. the cut point.
+                Err(e) if e.error_len().is_none() => Some(e.valid_up_to()),
+                Err(_) => None,
+            };
+            return match boundary.and_then(|b| std::str::from_utf8(&self.bytes[..b]).ok()) {
+                Some(text) => (text.to_string(), "utf8".to_string(), true),
+                None => {
+                    let encoded = base64::engine::general_purpose::STANDARD.encode(&self.bytes);
+                    (encoded, "base64".to_string(), true)
+                }
+            };
+        }
+
+        match String::from_utf8(self.bytes) {
+            Ok(text) => (text, "utf8".to_string(), false),
+            Err(e) => {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(e.into_bytes());
+                (encoded, "base64".to_string(), false)
+            }
+        }
+    }
 }
 
 #[tokio::main]
@@ -47,8 +167,8 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Relay service");
 
     // Configuration
-    let target_service_url = std::env::var("TARGET_SERVICE_URL")
-        .unwrap_or_else(|_| "http://localhost:3001".to_string());
+    let config_path = config::config_path();
+    let config = Config::load(&config_path)?;
     let service_name = std::env::var("SERVICE_NAME")
         .unwrap_or_else(|_| "unknown".to_string());
 
@@ -62,14 +182,36 @@ async fn main() -> anyhow::Result<()> {
         .create()
         .expect("Producer creation error");
 
-    let state = AppState {
-        target_service_url: target_service_url.clone(),
-        client: reqwest::Client::new(),
+    info!("Loaded config from {} ({} upstream(s), {} route(s))",
+          config_path.display(), config.upstreams.len(), config.routes.len());
+    info!("Service name: {}", service_name);
+
+    // message.timeout.ms-style request timeout so a hung upstream can't pin
+    // a connection forever; it also gives us an Error { kind: Timeout } to
+    // report instead of hanging silently.
+    let request_timeout_ms: u64 = std::env::var("REQUEST_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5000);
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(request_timeout_ms))
+        .build()
+        .expect("Failed to build reqwest client");
+
+    let state = Arc::new(AppState {
+        config: RwLock::new(config),
+        client,
         kafka_producer: Arc::new(producer),
-    };
+    });
 
-    info!("Target service: {}", target_service_url);
-    info!("Service name: {}", service_name);
+    // Reload the config on SIGHUP without dropping in-flight requests: only
+    // the RwLock is swapped, existing handlers keep running against the
+    // config snapshot they already read.
+    let reload_state = state.clone();
+    let reload_config_path = config_path.clone();
+    tokio::spawn(async move {
+        reload_on_sighup(reload_state, reload_config_path).await;
+    });
 
     // Build application
     let app = Router::new()
@@ -77,7 +219,7 @@ async fn main() -> anyhow::Result<()> {
         .route("/*path", get(relay_get).post(relay_post))
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
-        .with_state(Arc::new(state));
+        .with_state(state);
 
     // Start server
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
@@ -88,6 +230,29 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn reload_on_sighup(state: Arc<AppState>, config_path: std::path::PathBuf) {
+    let mut sighup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(signal) => signal,
+        Err(e) => {
+            error!("Failed to install SIGHUP handler: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        sighup.recv().await;
+        info!("Received SIGHUP, reloading config from {}", config_path.display());
+        match Config::load(&config_path) {
+            Ok(new_config) => {
+                let mut config = state.config.write().await;
+                *config = new_config;
+                info!("Config reloaded successfully");
+            }
+            Err(e) => error!("Failed to reload config, keeping previous config: {}", e),
+        }
+    }
+}
+
 async fn health_check() -> &'static str {
     "Relay is healthy"
 }
@@ -97,7 +262,7 @@ async fn relay_get(
     Path(path): Path<String>,
     Query(params): Query<HashMap<String, String>>,
     headers: HeaderMap,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Response {
     relay_request(state, "GET".to_string(), path, params, headers, None).await
 }
 
@@ -107,10 +272,15 @@ async fn relay_post(
     Query(params): Query<HashMap<String, String>>,
     headers: HeaderMap,
     body: String,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Response {
     relay_request(state, "POST".to_string(), path, params, headers, Some(body)).await
 }
 
+/// Header used both to honor a caller-supplied correlation id and to inject
+/// one outbound so the primary service's own access logs carry the same
+/// value as the Relay's and Monitor's logs.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
 async fn relay_request(
     state: Arc<AppState>,
     method: String,
@@ -118,14 +288,68 @@ async fn relay_request(
     params: HashMap<String, String>,
     headers: HeaderMap,
     body: Option<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let request_id = uuid::Uuid::new_v4().to_string();
-    let start_time = std::time::Instant::now();
+) -> Response {
+    // Reuse an incoming request id so the whole call chain - caller, primary,
+    // shadow, and the primary service's own access logs - share one
+    // correlation key. ULIDs are lexicographically time-sortable, unlike the
+    // UUIDv4s this used to mint, which makes ordering them in the Monitor
+    // straightforward.
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| ulid::Ulid::new().to_string());
 
     info!("Relaying {} request to /{} (request_id: {})", method, path, request_id);
 
-    // Build URL with query parameters
-    let mut url = format!("{}/{}", state.target_service_url, path);
+    let upstream = {
+        let config = state.config.read().await;
+        routing::select_upstream(&config, &path).clone()
+    };
+
+    // Fire the shadow call in the background so it never adds latency to the
+    // caller and its outcome (success or failure) never affects the response
+    // we give back.
+    let shadow_expected = upstream.shadow.is_some();
+    if let Some(shadow_url) = upstream.shadow.clone() {
+        tokio::spawn(dispatch_shadow(
+            state.clone(),
+            shadow_url,
+            request_id.clone(),
+            method.clone(),
+            path.clone(),
+            params.clone(),
+            headers.clone(),
+            body.clone(),
+        ));
+    }
+
+    let response = dispatch_primary(state, upstream.primary, request_id.clone(), method, path, params, headers, body, shadow_expected).await;
+    with_request_id_header(response, &request_id)
+}
+
+/// Echoes the correlation id back on the client-facing response so callers
+/// can report issues by id.
+fn with_request_id_header(mut response: Response, request_id: &str) -> Response {
+    if let Ok(value) = axum::http::HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Builds the `reqwest::RequestBuilder` shared by the primary and shadow
+/// dispatch paths. Fails only for HTTP methods the Relay doesn't route.
+fn build_request(
+    client: &reqwest::Client,
+    target_base_url: &str,
+    method: &str,
+    path: &str,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+    body: &Option<String>,
+    request_id: &str,
+) -> Result<reqwest::RequestBuilder, RelayOutcome> {
+    let mut url = format!("{}/{}", target_base_url, path);
     if !params.is_empty() {
         let query_string: String = params
             .iter()
@@ -135,101 +359,305 @@ async fn relay_request(
         url = format!("{}?{}", url, query_string);
     }
 
-    // Convert headers
-    let mut request_headers = HashMap::new();
-    for (name, value) in headers.iter() {
-        if let Ok(value_str) = value.to_str() {
-            request_headers.insert(name.to_string(), value_str.to_string());
-        }
-    }
-
-    // Make request to target service
-    let mut request_builder = match method.as_str() {
-        "GET" => state.client.get(&url),
-        "POST" => state.client.post(&url),
-        "PUT" => state.client.put(&url),
-        "DELETE" => state.client.delete(&url),
-        "PATCH" => state.client.patch(&url),
+    let mut request_builder = match method {
+        "GET" => client.get(&url),
+        "POST" => client.post(&url),
+        "PUT" => client.put(&url),
+        "DELETE" => client.delete(&url),
+        "PATCH" => client.patch(&url),
         _ => {
-            error!("Unsupported HTTP method: {}", method);
-            return Err(StatusCode::METHOD_NOT_ALLOWED);
+            return Err(RelayOutcome::Error {
+                kind: RelayErrorKind::Other,
+                message: format!("unsupported HTTP method: {}", method),
+            });
         }
     };
 
-    // Add headers (filter out problematic ones)
+    // Add headers (filter out problematic ones, and the correlation id which
+    // we set explicitly below so primary and shadow agree on one value
+    // regardless of what the caller sent)
     for (name, value) in headers.iter() {
         let name_str = name.as_str().to_lowercase();
         // Skip headers that can cause issues
-        if !["host", "content-length", "connection", "upgrade", "proxy-connection"].contains(&name_str.as_str()) {
+        if !["host", "content-length", "connection", "upgrade", "proxy-connection", REQUEST_ID_HEADER].contains(&name_str.as_str()) {
             request_builder = request_builder.header(name, value);
         }
     }
+    request_builder = request_builder.header(REQUEST_ID_HEADER, request_id);
 
-    // Add body if present
-    if let Some(body_content) = &body {
+    if let Some(body_content) = body {
         request_builder = request_builder.body(body_content.clone());
     }
 
-    // Execute request
-    match request_builder.send().await {
-        Ok(response) => {
-            let elapsed = start_time.elapsed();
-            let status = response.status();
-
-            // Collect response headers
-            let mut response_headers = HashMap::new();
-            for (name, value) in response.headers().iter() {
-                if let Ok(value_str) = value.to_str() {
-                    response_headers.insert(name.to_string(), value_str.to_string());
-                }
-            }
+    Ok(request_builder)
+}
 
-            // Get response body
-            let response_body = match response.text().await {
-                Ok(text) => text,
-                Err(e) => {
-                    error!("Failed to read response body: {}", e);
-                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
-                }
-            };
+fn collect_headers(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for (name, value) in headers.iter() {
+        if let Ok(value_str) = value.to_str() {
+            map.insert(name.to_string(), value_str.to_string());
+        }
+    }
+    map
+}
 
-            // Log the relay information (this is where you'll implement actual logging/storage)
-            let relay_log = RelayLog {
-                request_id: request_id.clone(),
-                service_name: std::env::var("SERVICE_NAME").unwrap_or_else(|_| "unknown".to_string()),
-                timestamp: chrono::Utc::now(),
-                method: method.clone(),
-                path: path.clone(),
-                request_headers,
-                request_body: body,
-                response_status: status.as_u16(),
-                response_headers,
-                response_body: response_body.clone(),
-                response_time_ms: elapsed.as_millis() as u64,
-            };
+fn classify_send_error(e: &reqwest::Error) -> RelayErrorKind {
+    if e.is_timeout() {
+        RelayErrorKind::Timeout
+    } else if e.is_connect() {
+        RelayErrorKind::Connect
+    } else {
+        RelayErrorKind::Other
+    }
+}
+
+/// Turns a finished capture into the outcome/body/encoding/truncated tuple
+/// used for the Kafka log (the client gets the streamed chunks themselves).
+fn finalize_capture(status: StatusCode, captured: CapturedBody) -> (RelayOutcome, String, String, bool) {
+    let (body_text, encoding, truncated) = captured.into_log_fields();
+    let outcome = if status.is_success() {
+        RelayOutcome::Success
+    } else {
+        RelayOutcome::BadResponse { status: status.as_u16(), body: body_text.clone() }
+    };
+    (outcome, body_text, encoding, truncated)
+}
+
+/// Sends the request to the primary upstream and returns a response that
+/// preserves the upstream's status code and (non-hop-by-hop) headers,
+/// streaming the body through rather than buffering it. A bounded copy of
+/// the body is captured as it streams by so the Kafka log never has to
+/// block on - or re-read - the whole thing.
+async fn dispatch_primary(
+    state: Arc<AppState>,
+    target_base_url: String,
+    request_id: String,
+    method: String,
+    path: String,
+    params: HashMap<String, String>,
+    headers: HeaderMap,
+    body: Option<String>,
+    shadow_expected: bool,
+) -> Response {
+    let start_time = std::time::Instant::now();
+    let request_headers = collect_headers(&headers);
+
+    let request_builder = match build_request(&state.client, &target_base_url, &method, &path, &params, &headers, &body, &request_id) {
+        Ok(rb) => rb,
+        Err(outcome) => {
+            error!("Cannot build primary request: unsupported method {}", method);
+            log_outcome(&state, &request_id, "primary", &method, &path, request_headers, body, outcome, None, String::new(), "utf8".to_string(), false, HashMap::new(), start_time.elapsed(), shadow_expected).await;
+            return StatusCode::METHOD_NOT_ALLOWED.into_response();
+        }
+    };
+
+    let response = match request_builder.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to relay request to {}: {}", target_base_url, e);
+            let outcome = RelayOutcome::Error { kind: classify_send_error(&e), message: e.to_string() };
+            log_outcome(&state, &request_id, "primary", &method, &path, request_headers, body, outcome, None, String::new(), "utf8".to_string(), false, HashMap::new(), start_time.elapsed(), shadow_expected).await;
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
 
-            // Send relay log to Kafka
-            if let Err(e) = send_to_kafka(&state.kafka_producer, &relay_log).await {
-                error!("Failed to send relay log to Kafka: {}", e);
+    let status = response.status();
+    let response_headers = collect_headers(response.headers());
+
+    let mut response_builder = Response::builder().status(status);
+    for (name, value) in response.headers().iter() {
+        if !HOP_BY_HOP_HEADERS.contains(&name.as_str().to_lowercase().as_str()) {
+            response_builder = response_builder.header(name, value);
+        }
+    }
+
+    let mut upstream_stream = response.bytes_stream();
+
+    // Read from the upstream - and capture/log the outcome - in a task of
+    // its own rather than the generator that feeds `Body::from_stream`: if
+    // reading and yielding lived in the same generator, it would only ever
+    // make progress when the client drains the response, which would (a)
+    // bake the client's download time into `response_time_ms`, skewing
+    // `latency_delta_ms` against the shadow's server-side-only timing, and
+    // (b) mean a client that disconnects early drops the relay log
+    // entirely. The bounded channel lets this task run ahead of a slow
+    // client - so `response_time_ms` reflects upstream time for anything up
+    // to a buffer's worth of lead - while still applying backpressure
+    // instead of buffering an arbitrarily large body in memory.
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<bytes::Bytes, std::io::Error>>(RELAY_STREAM_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        let mut captured = CapturedBody::default();
+        let mut read_error = None;
+
+        while let Some(chunk_result) = upstream_stream.next().await {
+            match chunk_result {
+                Ok(chunk) => {
+                    captured.push(&chunk);
+                    // Best-effort: the client may already be gone.
+                    let _ = tx.send(Ok(chunk)).await;
+                }
+                Err(e) => {
+                    error!("Error while streaming response body for {}: {}", path, e);
+                    read_error = Some(e.to_string());
+                    break;
+                }
             }
+        }
 
-            info!("Relay log: request_id={}, status={}, response_time={}ms",
-                  request_id, status.as_u16(), elapsed.as_millis());
+        let elapsed = start_time.elapsed();
+        let (outcome, response_body, response_body_encoding, response_body_truncated) = match read_error {
+            Some(message) => (
+                RelayOutcome::Error { kind: RelayErrorKind::BodyRead, message },
+                String::new(),
+                "utf8".to_string(),
+                captured.truncated,
+            ),
+            None => finalize_capture(status, captured),
+        };
+
+        log_outcome(
+            &state, &request_id, "primary", &method, &path,
+            request_headers, body, outcome, Some(status.as_u16()),
+            response_body, response_body_encoding, response_body_truncated,
+            response_headers, elapsed, shadow_expected,
+        ).await;
+    });
+
+    let relay_stream = async_stream::stream! {
+        while let Some(chunk) = rx.recv().await {
+            yield chunk;
+        }
+    };
 
-            // Try to parse response as JSON, fallback to string
-            let json_response = if let Ok(json) = serde_json::from_str::<serde_json::Value>(&response_body) {
-                json
-            } else {
-                serde_json::json!({ "data": response_body })
-            };
+    response_builder
+        .body(Body::from_stream(relay_stream))
+        .unwrap_or_else(|e| {
+            error!("Failed to build relay response: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })
+}
 
-            Ok(Json(json_response))
+/// Sends the request to the shadow upstream and only ever logs the outcome
+/// to Kafka - nothing here can affect what the caller already received from
+/// the primary.
+async fn dispatch_shadow(
+    state: Arc<AppState>,
+    target_base_url: String,
+    request_id: String,
+    method: String,
+    path: String,
+    params: HashMap<String, String>,
+    headers: HeaderMap,
+    body: Option<String>,
+) {
+    let start_time = std::time::Instant::now();
+    let request_headers = collect_headers(&headers);
+
+    let request_builder = match build_request(&state.client, &target_base_url, &method, &path, &params, &headers, &body, &request_id) {
+        Ok(rb) => rb,
+        Err(outcome) => {
+            warn!("Shadow relay failed for request_id {}: unsupported method {}", request_id, method);
+            log_outcome(&state, &request_id, "shadow", &method, &path, request_headers, body, outcome, None, String::new(), "utf8".to_string(), false, HashMap::new(), start_time.elapsed(), true).await;
+            return;
         }
+    };
+
+    let response = match request_builder.send().await {
+        Ok(response) => response,
         Err(e) => {
-            error!("Failed to relay request to {}: {}", url, e);
-            Err(StatusCode::BAD_GATEWAY)
+            warn!("Shadow relay failed for request_id {}: {}", request_id, e);
+            let outcome = RelayOutcome::Error { kind: classify_send_error(&e), message: e.to_string() };
+            log_outcome(&state, &request_id, "shadow", &method, &path, request_headers, body, outcome, None, String::new(), "utf8".to_string(), false, HashMap::new(), start_time.elapsed(), true).await;
+            return;
         }
+    };
+
+    let status = response.status();
+    let response_headers = collect_headers(response.headers());
+
+    let mut captured = CapturedBody::default();
+    let mut upstream_stream = response.bytes_stream();
+    let mut read_error = None;
+    while let Some(chunk_result) = upstream_stream.next().await {
+        match chunk_result {
+            Ok(chunk) => captured.push(&chunk),
+            Err(e) => {
+                read_error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    let (outcome, response_body, response_body_encoding, response_body_truncated) = match read_error {
+        Some(message) => (
+            RelayOutcome::Error { kind: RelayErrorKind::BodyRead, message },
+            String::new(),
+            "utf8".to_string(),
+            captured.truncated,
+        ),
+        None => finalize_capture(status, captured),
+    };
+
+    if let RelayOutcome::Error { ref message, .. } = outcome {
+        warn!("Shadow relay failed for request_id {}: {}", request_id, message);
     }
+
+    log_outcome(
+        &state, &request_id, "shadow", &method, &path,
+        request_headers, body, outcome, Some(status.as_u16()),
+        response_body, response_body_encoding, response_body_truncated,
+        response_headers, start_time.elapsed(), true,
+    )
+    .await;
+}
+
+/// Builds a `RelayLog` from a dispatch's outcome and sends it to Kafka. This
+/// always runs, including on connect/timeout/body-read failures, so the
+/// Monitor never silently misses that a relay failed.
+#[allow(clippy::too_many_arguments)]
+async fn log_outcome(
+    state: &Arc<AppState>,
+    request_id: &str,
+    role: &str,
+    method: &str,
+    path: &str,
+    request_headers: HashMap<String, String>,
+    request_body: Option<String>,
+    outcome: RelayOutcome,
+    status: Option<u16>,
+    response_body: String,
+    response_body_encoding: String,
+    response_body_truncated: bool,
+    response_headers: HashMap<String, String>,
+    elapsed: std::time::Duration,
+    shadow_expected: bool,
+) {
+    let relay_log = RelayLog {
+        request_id: request_id.to_string(),
+        service_name: std::env::var("SERVICE_NAME").unwrap_or_else(|_| "unknown".to_string()),
+        role: role.to_string(),
+        timestamp: chrono::Utc::now(),
+        method: method.to_string(),
+        path: path.to_string(),
+        request_headers,
+        request_body,
+        outcome,
+        response_status: status.unwrap_or(0),
+        response_headers,
+        response_body,
+        response_body_encoding,
+        response_body_truncated,
+        response_time_ms: elapsed.as_millis() as u64,
+        shadow_expected,
+    };
+
+    if let Err(e) = send_to_kafka(&state.kafka_producer, &relay_log).await {
+        error!("Failed to send relay log to Kafka: {}", e);
+    }
+
+    info!("Relay log: request_id={}, role={}, status={}, response_time={}ms",
+          request_id, role, relay_log.response_status, elapsed.as_millis());
 }
 
 async fn send_to_kafka(producer: &FutureProducer, log: &RelayLog) -> anyhow::Result<()> {
@@ -257,4 +685,4 @@ async fn send_to_kafka(producer: &FutureProducer, log: &RelayLog) -> anyhow::Res
 
 // async fn store_relay_log(log: &RelayLog) -> anyhow::Result<()> {
 //     // Store to database for persistence
-// }
\ No newline at end of file
+// }
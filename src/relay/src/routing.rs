@@ -0,0 +1,22 @@
+//! Selects which upstream pair handles an incoming request path.
+
+use crate::config::{Config, Upstream};
+
+/// Returns the upstream whose route prefix is the longest match for `path`,
+/// falling back to `default_upstream` when nothing matches.
+pub fn select_upstream<'a>(config: &'a Config, path: &str) -> &'a Upstream {
+    let full_path = format!("/{}", path);
+
+    let matched_name = config
+        .routes
+        .iter()
+        .filter(|route| full_path.starts_with(route.prefix.as_str()))
+        .max_by_key(|route| route.prefix.len())
+        .map(|route| route.upstream.as_str())
+        .unwrap_or(config.default_upstream.as_str());
+
+    config
+        .upstreams
+        .get(matched_name)
+        .expect("route upstream validated against config.upstreams at load time")
+}